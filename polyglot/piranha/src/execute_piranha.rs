@@ -0,0 +1,159 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
+
+use log::info;
+use rayon::prelude::*;
+
+use crate::models::{
+  license_header, manifest, piranha_arguments::PiranhaArguments,
+  piranha_output::PiranhaOutputSummary, rule::Rule,
+  rule_store::{RuleStore, RuleStoreCore},
+};
+
+/// Runs Piranha end-to-end for `args`.
+///
+/// Builds the (immutable, `Sync`) `RuleStoreCore` once, then fans the input files out
+/// across `args.thread_count()` worker threads - each file's rule-graph traversal is
+/// independent of every other file's, so every thread gets its own `RuleStore` seeded
+/// from the shared core rather than contending over one. If any file discovers a new
+/// global rule or global tag along the way, those deltas are merged back in
+/// deterministically after the pass. A second pass only runs if a genuinely new
+/// global rule was discovered (one not already part of the seed rule set or an
+/// earlier pass's delta); a file merely producing a global tag doesn't, on its own,
+/// warrant revisiting every file.
+pub fn execute_piranha(args: &PiranhaArguments) -> Vec<PiranhaOutputSummary> {
+  let core = RuleStoreCore::new(args);
+  let paths = args.paths_to_process();
+
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(*args.thread_count())
+    .build()
+    .expect("Could not build the Piranha thread pool");
+
+  let (mut summaries, new_global_rules, mut merged_global_tags) =
+    pool.install(|| run_pass(&core, &paths, &[], &HashMap::new()));
+
+  if !new_global_rules.is_empty() {
+    info!(
+      "{} new global rule(s) discovered across files - running a second pass",
+      new_global_rules.len()
+    );
+    let (mut second_pass_summaries, _, second_pass_tags) =
+      pool.install(|| run_pass(&core, &paths, &new_global_rules, &merged_global_tags));
+    summaries.append(&mut second_pass_summaries);
+    merged_global_tags.extend(second_pass_tags);
+  }
+
+  emit_manifest_if_requested(args, &core, &merged_global_tags, &summaries);
+  summaries
+}
+
+/// If `args` was given a manifest output path, builds the run's transformation
+/// manifest from `summaries` and writes it there. Opt-in and run once, after the run
+/// (including any second pass) has fully completed, so the manifest reflects every
+/// edit Piranha actually applied.
+fn emit_manifest_if_requested(
+  args: &PiranhaArguments, core: &RuleStoreCore, global_tags: &HashMap<String, String>,
+  summaries: &[PiranhaOutputSummary],
+) {
+  let Some(output_path) = args.manifest_output_path() else {
+    return;
+  };
+  let (rule_count, edge_count) = core.rule_graph().get_number_of_rules_and_edges();
+  let manifest = manifest::build_manifest(
+    args.piranha_language().name(),
+    &[args.path_to_configurations().to_string()],
+    rule_count,
+    edge_count,
+    global_tags,
+    summaries,
+  );
+  if let Err(e) = manifest::write_manifest(&manifest, output_path) {
+    log::error!("Could not write transformation manifest to {output_path:?}: {e}");
+  }
+}
+
+/// Processes every file in `paths` in parallel against the shared `core`, seeding
+/// each file-level `RuleStore` with `extra_global_rules`/`extra_global_tags` (the
+/// deltas from a previous pass, if any), then merges each file's own global-rule and
+/// global-tag deltas deterministically - rules are deduped by name and sorted, so the
+/// merged result never depends on thread scheduling order.
+fn run_pass(
+  core: &Arc<RuleStoreCore>, paths: &[PathBuf], extra_global_rules: &[Rule],
+  extra_global_tags: &HashMap<String, String>,
+) -> (Vec<PiranhaOutputSummary>, Vec<Rule>, HashMap<String, String>) {
+  let per_file_results: Vec<(Vec<PiranhaOutputSummary>, Vec<Rule>, HashMap<String, String>)> =
+    paths
+      .par_iter()
+      .map(|path| {
+        let mut rule_store = RuleStore::from_core_with_additional_globals(
+          core.clone(),
+          extra_global_rules,
+          extra_global_tags,
+        );
+        let summary = crate::parse_file_and_apply_rules(path, &mut rule_store);
+        let summary = apply_spdx_header_if_configured(core, path, summary);
+        let (global_rules, global_tags) = rule_store.into_global_deltas();
+        (summary, global_rules, global_tags)
+      })
+      .collect();
+
+  let mut summaries = Vec::with_capacity(per_file_results.len());
+  let mut merged_rules: HashMap<String, Rule> = HashMap::new();
+  let mut merged_tags: HashMap<String, String> = HashMap::new();
+  for (summary, rules, tags) in per_file_results {
+    summaries.extend(summary);
+    for rule in rules {
+      merged_rules.entry(rule.name()).or_insert(rule);
+    }
+    merged_tags.extend(tags);
+  }
+
+  let mut new_global_rules: Vec<Rule> = merged_rules.into_values().collect();
+  new_global_rules.sort_by_key(|r| r.name());
+
+  (summaries, new_global_rules, merged_tags)
+}
+
+/// If `core`'s args configure a `SpdxHeaderConfig`, enforces/normalizes `path`'s license
+/// header as a built-in cleanup, writing the result to disk and appending its own
+/// `PiranhaOutputSummary` to `summaries` - the same opt-in shape as
+/// `emit_manifest_if_requested`, but applied per-file alongside the rule graph rather
+/// than once at the end of the run.
+fn apply_spdx_header_if_configured(
+  core: &RuleStoreCore, path: &PathBuf, mut summaries: Vec<PiranhaOutputSummary>,
+) -> Vec<PiranhaOutputSummary> {
+  let Some(config) = core.piranha_args().spdx_header_config() else {
+    return summaries;
+  };
+  let Ok(content) = fs::read_to_string(path) else {
+    return summaries;
+  };
+  let Some(edit) = license_header::spdx_header_edit(&content, core.piranha_args().piranha_language(), config)
+  else {
+    return summaries;
+  };
+
+  let range = edit.p_match().range();
+  let mut new_content = content;
+  new_content.replace_range(range.start_byte..range.end_byte, edit.replacement_string());
+
+  if let Err(e) = fs::write(path, &new_content) {
+    log::error!("Could not write normalized license header to {path:?}: {e}");
+    return summaries;
+  }
+  summaries.push(PiranhaOutputSummary::new(path.clone(), new_content, vec![edit]));
+  summaries
+}
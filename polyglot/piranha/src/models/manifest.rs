@@ -0,0 +1,173 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use serde_derive::Serialize;
+
+use super::{edit::Edit, piranha_output::PiranhaOutputSummary};
+
+/// Bumped whenever `TransformationManifest`'s schema changes in a way downstream
+/// tooling should be aware of.
+static MANIFEST_SCHEMA_VERSION: &str = "1.0";
+static GENERATOR_NAME: &str = "polyglot-piranha";
+
+/// A single machine-readable artifact enumerating everything a Piranha run changed,
+/// modeled on SBOM/manifest generators: a top-level document carrying run-level
+/// metadata plus a flat list of per-file `FileTransformations`, so downstream tooling
+/// can audit or diff what a run did across a codebase. Opt-in - only produced when the
+/// caller configures a manifest output path.
+#[derive(Debug, Serialize)]
+pub(crate) struct TransformationManifest {
+  schema_version: &'static str,
+  generator: &'static str,
+  generator_version: &'static str,
+  language: String,
+  config_paths: Vec<String>,
+  rule_count: usize,
+  edge_count: usize,
+  global_tags: HashMap<String, String>,
+  files: Vec<FileTransformations>,
+}
+
+/// Every edit Piranha applied to a single file, in the order they were applied.
+#[derive(Debug, Serialize)]
+pub(crate) struct FileTransformations {
+  path: String,
+  edits: Vec<TransformationRecord>,
+}
+
+/// A single applied edit's provenance: the rule that produced it, where it landed,
+/// and what it replaced the matched text with.
+#[derive(Debug, Serialize)]
+pub(crate) struct TransformationRecord {
+  rule_name: String,
+  start_byte: usize,
+  end_byte: usize,
+  start_point: (usize, usize),
+  end_point: (usize, usize),
+  replacement: String,
+}
+
+impl From<&Edit> for TransformationRecord {
+  fn from(edit: &Edit) -> Self {
+    let range = edit.p_match().range();
+    TransformationRecord {
+      rule_name: edit.matched_rule().to_string(),
+      start_byte: range.start_byte,
+      end_byte: range.end_byte,
+      start_point: (range.start_point.row, range.start_point.column),
+      end_point: (range.end_point.row, range.end_point.column),
+      replacement: edit.replacement_string().to_string(),
+    }
+  }
+}
+
+/// Builds the manifest for a completed run from its output `summaries`, plus the
+/// run-level metadata that isn't carried by any individual summary.
+pub(crate) fn build_manifest(
+  language: &str, config_paths: &[String], rule_count: usize, edge_count: usize,
+  global_tags: &HashMap<String, String>, summaries: &[PiranhaOutputSummary],
+) -> TransformationManifest {
+  let files = merge_by_path(summaries);
+
+  TransformationManifest {
+    schema_version: MANIFEST_SCHEMA_VERSION,
+    generator: GENERATOR_NAME,
+    generator_version: env!("CARGO_PKG_VERSION"),
+    language: language.to_string(),
+    config_paths: config_paths.to_vec(),
+    rule_count,
+    edge_count,
+    global_tags: global_tags.clone(),
+    files,
+  }
+}
+
+/// Merges `summaries` into one `FileTransformations` per distinct path, in first-seen
+/// order, concatenating the edits of every summary for a given path in the order the
+/// summaries appear (e.g. a first pass's summary followed by a second pass's for the
+/// same file) - a file Piranha touched more than once should show up once in the
+/// manifest, with its full edit history, rather than once per summary.
+fn merge_by_path(summaries: &[PiranhaOutputSummary]) -> Vec<FileTransformations> {
+  merge_entries_by_path(summaries.iter().map(|summary| {
+    (
+      summary.path().to_string_lossy().to_string(),
+      summary.rewrites().iter().map(TransformationRecord::from).collect(),
+    )
+  }))
+}
+
+/// The pure, `PiranhaOutputSummary`-free half of `merge_by_path`: groups `(path, edits)`
+/// entries by path, in first-seen order, concatenating the edits of every entry for a
+/// given path in the order the entries appear. Split out so the merge logic can be
+/// unit-tested without a `PiranhaOutputSummary`/`Edit`.
+fn merge_entries_by_path(
+  entries: impl Iterator<Item = (String, Vec<TransformationRecord>)>,
+) -> Vec<FileTransformations> {
+  let mut by_path: HashMap<String, usize> = HashMap::new();
+  let mut files: Vec<FileTransformations> = Vec::new();
+
+  for (path, edits) in entries {
+    match by_path.get(&path) {
+      Some(&index) => files[index].edits.extend(edits),
+      None => {
+        by_path.insert(path.clone(), files.len());
+        files.push(FileTransformations { path, edits });
+      }
+    }
+  }
+
+  files
+}
+
+/// Serializes `manifest` as pretty-printed JSON and writes it to `output_path`.
+pub(crate) fn write_manifest(manifest: &TransformationManifest, output_path: &Path) -> io::Result<()> {
+  let json = serde_json::to_string_pretty(manifest)
+    .expect("Could not serialize the transformation manifest");
+  fs::write(output_path, json)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{merge_entries_by_path, TransformationRecord};
+
+  fn record(rule_name: &str) -> TransformationRecord {
+    TransformationRecord {
+      rule_name: rule_name.to_string(),
+      start_byte: 0,
+      end_byte: 0,
+      start_point: (0, 0),
+      end_point: (0, 0),
+      replacement: String::new(),
+    }
+  }
+
+  #[test]
+  fn merges_entries_for_the_same_path_into_one_file_preserving_edit_order() {
+    let entries = vec![
+      ("a.rs".to_string(), vec![record("first_pass_rule")]),
+      ("b.rs".to_string(), vec![record("unrelated_rule")]),
+      ("a.rs".to_string(), vec![record("second_pass_rule")]),
+    ];
+
+    let files = merge_entries_by_path(entries.into_iter());
+
+    assert_eq!(files.len(), 2, "a.rs's two entries should merge into one file");
+    assert_eq!(files[0].path, "a.rs");
+    let rule_names: Vec<_> = files[0].edits.iter().map(|e| e.rule_name.as_str()).collect();
+    assert_eq!(rule_names, vec!["first_pass_rule", "second_pass_rule"]);
+    assert_eq!(files[1].path, "b.rs");
+    assert_eq!(files[1].edits.len(), 1);
+  }
+}
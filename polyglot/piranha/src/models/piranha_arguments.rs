@@ -0,0 +1,111 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::{collections::HashMap, path::PathBuf};
+
+use derive_builder::Builder;
+use getset::Getters;
+
+use super::language::PiranhaLanguage;
+use super::license_header::SpdxHeaderConfig;
+
+/// Number of worker threads `execute_piranha` fans per-file work out across when the
+/// caller doesn't override `thread_count` explicitly.
+const DEFAULT_THREAD_COUNT: usize = 4;
+
+/// The fully-resolved configuration for one Piranha run. Cheap to `Clone` - shared
+/// across every file-level `RuleStore` in a run (and, via `RuleStoreCore`, across the
+/// worker threads `execute_piranha` fans per-file work out to).
+#[derive(Builder, Getters, Clone, Debug)]
+pub(crate) struct PiranhaArguments {
+  /// The tree-sitter language Piranha is configured for.
+  #[get = "pub"]
+  piranha_language: PiranhaLanguage,
+
+  /// Path to the directory containing the `rules.toml`/`edges.toml` for this run.
+  #[get = "pub"]
+  #[builder(default = "String::new()")]
+  path_to_configurations: String,
+
+  /// Files Piranha should process, already resolved from the codebase root.
+  #[get = "pub"]
+  #[builder(default)]
+  paths_to_process: Vec<PathBuf>,
+
+  /// Tag substitutions supplied by the caller, merged into every rule's own tag
+  /// matches before instantiation.
+  #[get = "pub"]
+  #[builder(default)]
+  input_substitutions: HashMap<String, String>,
+
+  /// Prefix that marks a tag capture as a *global* tag - visible to every rule that
+  /// runs afterwards in the same pass, not just the rule that captured it.
+  #[get = "pub"]
+  #[builder(default = "\"GLOBAL_TAG.\".to_string()")]
+  global_tag_prefix: String,
+
+  /// Number of worker threads `execute_piranha` fans per-file work out across, since
+  /// each file's rule-graph traversal is independent of every other file's.
+  #[get = "pub"]
+  #[builder(default = "DEFAULT_THREAD_COUNT")]
+  thread_count: usize,
+
+  /// Where to write the run's structured transformation manifest, if requested.
+  /// `None` (the default) means no manifest is produced.
+  #[get = "pub"]
+  #[builder(default)]
+  manifest_output_path: Option<PathBuf>,
+
+  /// Enforces/normalizes a REUSE-style SPDX license header on every processed file
+  /// as a built-in cleanup run alongside the rule graph, if requested. `None` (the
+  /// default) leaves license headers untouched.
+  #[get = "pub"]
+  #[builder(default)]
+  spdx_header_config: Option<SpdxHeaderConfig>,
+}
+
+impl PiranhaArguments {
+  /// Returns a copy of these args with `paths_to_process` narrowed to `paths`,
+  /// leaving every other field untouched - used to re-run the rule graph against
+  /// just the file(s) a cleanup round touched, rather than the whole input set.
+  pub(crate) fn with_paths_to_process(&self, paths_to_process: Vec<PathBuf>) -> Self {
+    Self {
+      paths_to_process,
+      ..self.clone()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn with_paths_to_process_only_replaces_paths() {
+    let args = PiranhaArgumentsBuilder::default()
+      .piranha_language(PiranhaLanguage::default())
+      .path_to_configurations("configs/".to_string())
+      .paths_to_process(vec![PathBuf::from("a.rs")])
+      .global_tag_prefix("TAG.".to_string())
+      .thread_count(8_usize)
+      .build()
+      .unwrap();
+
+    let narrowed = args.with_paths_to_process(vec![PathBuf::from("b.rs")]);
+
+    assert_eq!(narrowed.paths_to_process(), &vec![PathBuf::from("b.rs")]);
+    assert_eq!(narrowed.path_to_configurations(), args.path_to_configurations());
+    assert_eq!(narrowed.global_tag_prefix(), args.global_tag_prefix());
+    assert_eq!(*narrowed.thread_count(), *args.thread_count());
+  }
+}
@@ -11,7 +11,11 @@ Copyright (c) 2022 Uber Technologies, Inc.
  limitations under the License.
 */
 
-use std::{collections::HashMap, path::Path};
+use std::{
+  collections::HashMap,
+  path::Path,
+  sync::{Arc, Mutex},
+};
 
 use colored::Colorize;
 use getset::Getters;
@@ -36,67 +40,167 @@ use super::{
 
 pub(crate) static GLOBAL: &str = "Global";
 pub(crate) static PARENT: &str = "Parent";
-/// This maintains the state for Piranha.
-#[derive(Debug, Getters)]
-pub(crate) struct RuleStore {
+
+/// The portion of `RuleStore`'s state that never changes once Piranha has read its
+/// configuration - the rule graph, the rule lookup table, the scope generators, the
+/// language and the CLI args. It is read-only and therefore `Sync`, so it is shared
+/// across worker threads behind an `Arc` rather than rebuilt (or locked) per file.
+///
+/// The compiled-query cache also lives here: every thread populates the same cache,
+/// so it's guarded by a `Mutex` instead of requiring `&mut self` on `RuleStore::query`.
+#[derive(Debug)]
+pub(crate) struct RuleStoreCore {
   // A graph that captures the flow amongst the rules
   rule_graph: RuleGraph,
-  // Caches the compiled tree-sitter queries.
-  rule_query_cache: HashMap<String, Query>,
   // All the input rules stored by name
   rules_by_name: HashMap<String, Rule>,
-  // Current global rules to be applied.
-  #[get = "pub"]
-  global_rules: Vec<Rule>,
   // Scope generators.
   scopes: Vec<ScopeGenerator>,
   // Command line arguments passed to piranha
-  #[get = "pub"]
   piranha_args: PiranhaArguments,
-  // Command line arguments passed to piranha
-  #[get = "pub"]
-  global_tags: HashMap<String, String>,
   /// Tree-sitter language model
-  #[get = "pub"]
   language: Language,
+  // Caches the compiled tree-sitter queries, shared (and written to) by every worker thread.
+  rule_query_cache: Mutex<HashMap<String, Arc<Query>>>,
+  // Names of the rules that were seed rules from the start, so that
+  // `RuleStore::into_global_deltas` can tell a file-level store's baseline global
+  // rules apart from rules it genuinely discovered while processing its file.
+  seed_rule_names: std::collections::HashSet<String>,
 }
 
-impl RuleStore {
-  pub(crate) fn new(args: &PiranhaArguments) -> RuleStore {
+impl RuleStoreCore {
+  pub(crate) fn new(args: &PiranhaArguments) -> Arc<RuleStoreCore> {
     let (rules, edges, scopes) = read_config_files(args);
     let rule_graph = RuleGraph::new(&edges, &rules);
-    let mut rule_store = RuleStore {
+    let seed_rule_names = rules
+      .iter()
+      .filter(|r| r.is_seed_rule())
+      .map(|r| r.name())
+      .collect();
+    let core = RuleStoreCore {
       rule_graph,
       rules_by_name: rules.iter().map(|r| (r.name(), r.clone())).collect(),
       scopes,
       piranha_args: args.clone(),
       language: *args.piranha_language().language(),
-      ..Default::default()
+      rule_query_cache: Mutex::new(HashMap::new()),
+      seed_rule_names,
+    };
+    info!(
+      "Number of rules and edges loaded : {:?}",
+      core.rule_graph.get_number_of_rules_and_edges()
+    );
+    trace!("Rule Store Core {}", format!("{:#?}", core));
+    Arc::new(core)
+  }
+
+  pub(crate) fn rule_graph(&self) -> &RuleGraph {
+    &self.rule_graph
+  }
+
+  /// Exposes the run's `PiranhaArguments` to callers (e.g. `execute_piranha::run_pass`)
+  /// that only hold the shared core and haven't built a per-file `RuleStore` yet -
+  /// `RuleStore::piranha_args` covers the same need once one exists.
+  pub(crate) fn piranha_args(&self) -> &PiranhaArguments {
+    &self.piranha_args
+  }
+}
+
+impl Default for RuleStoreCore {
+  fn default() -> Self {
+    RuleStoreCore {
+      rule_graph: RuleGraph::default(),
+      rules_by_name: HashMap::default(),
+      piranha_args: PiranhaArgumentsBuilder::default().build().unwrap(),
+      scopes: Vec::default(),
+      language: *PiranhaLanguage::default().language(),
+      rule_query_cache: Mutex::new(HashMap::default()),
+      seed_rule_names: std::collections::HashSet::default(),
+    }
+  }
+}
+
+/// Maintains the per-file state for Piranha: the global rules and tags as discovered
+/// by this one file, layered on top of the `RuleStoreCore` shared (read-only) with
+/// every other file in the same run. Each worker thread owns its own `RuleStore`, so
+/// a file's rule-graph traversal never blocks on another file's; `global_rules` and
+/// `global_tags` are reconciled across files only after the parallel pass completes.
+#[derive(Debug, Getters)]
+pub(crate) struct RuleStore {
+  core: Arc<RuleStoreCore>,
+  // Current global rules to be applied, as discovered by this file.
+  #[get = "pub"]
+  global_rules: Vec<Rule>,
+  // Current global tags, as discovered by this file.
+  #[get = "pub"]
+  global_tags: HashMap<String, String>,
+  // Names of the rules this store was seeded with (the core's seed rules, plus any
+  // rules a previous pass already discovered) - i.e. the baseline `into_global_deltas`
+  // diffs against to report only genuinely *new* rules.
+  baseline_rule_names: std::collections::HashSet<String>,
+}
+
+impl RuleStore {
+  pub(crate) fn new(args: &PiranhaArguments) -> RuleStore {
+    RuleStore::from_core(RuleStoreCore::new(args))
+  }
+
+  /// Creates a fresh per-file view over a `RuleStoreCore` shared with sibling files
+  /// in the same (possibly parallel) run, seeding its global rules the same way
+  /// `RuleStore::new` does.
+  pub(crate) fn from_core(core: Arc<RuleStoreCore>) -> RuleStore {
+    RuleStore::from_core_with_additional_globals(core, &Vec::new(), &HashMap::new())
+  }
+
+  /// Like `from_core`, but also seeds `additional_rules`/`additional_tags` - the
+  /// merged deltas a previous parallel pass discovered - so a second pass gives every
+  /// file a chance to match against global rules that were only added partway through
+  /// the first one. `additional_rules` count towards this store's baseline, not its
+  /// delta - a second pass re-seeding an already-known rule isn't a *new* discovery.
+  pub(crate) fn from_core_with_additional_globals(
+    core: Arc<RuleStoreCore>, additional_rules: &[Rule], additional_tags: &HashMap<String, String>,
+  ) -> RuleStore {
+    let mut baseline_rule_names = core.seed_rule_names.clone();
+    baseline_rule_names.extend(additional_rules.iter().map(|r| r.name()));
+
+    let mut rule_store = RuleStore {
+      global_rules: Vec::new(),
+      global_tags: additional_tags.clone(),
+      core,
+      baseline_rule_names,
     };
 
-    for (_, rule) in rule_store.rules_by_name.clone() {
+    for (_, rule) in rule_store.core.rules_by_name.clone() {
       if rule.is_seed_rule() {
-        rule_store.add_to_global_rules(&rule, args.input_substitutions());
+        let substitutions = rule_store.core.piranha_args.input_substitutions().clone();
+        rule_store.add_to_global_rules(&rule, &substitutions);
       }
     }
-    info!(
-      "Number of rules and edges loaded : {:?}",
-      rule_store.rule_graph.get_number_of_rules_and_edges()
-    );
-    trace!("Rule Store {}", format!("{:#?}", rule_store));
+    for rule in additional_rules {
+      let substitutions = rule_store.default_substitutions();
+      rule_store.add_to_global_rules(rule, &substitutions);
+    }
     rule_store
   }
 
   #[cfg(test)]
   pub(crate) fn default_with_scopes(scopes: Vec<ScopeGenerator>) -> RuleStore {
-    RuleStore {
+    RuleStore::from_core(Arc::new(RuleStoreCore {
       scopes,
       ..Default::default()
-    }
+    }))
+  }
+
+  pub(crate) fn piranha_args(&self) -> &PiranhaArguments {
+    &self.core.piranha_args
+  }
+
+  pub(crate) fn language(&self) -> &Language {
+    &self.core.language
   }
 
   pub(crate) fn default_substitutions(&self) -> HashMap<String, String> {
-    let mut default_subs = self.piranha_args.input_substitutions().clone();
+    let mut default_subs = self.core.piranha_args.input_substitutions().clone();
     default_subs.extend(self.global_tags().clone());
     default_subs
   }
@@ -114,18 +218,28 @@ impl RuleStore {
     }
   }
 
-  /// Get the compiled query for the `query_str` from the cache
-  /// else compile it, add it to the cache and return it.
-  pub(crate) fn query(&mut self, query_str: &String) -> &Query {
+  /// Get the compiled query for the `query_str` from the shared cache, else compile
+  /// it, add it to the cache and return it. Takes `&self` rather than `&mut self` -
+  /// the cache is a `Mutex`-guarded map shared by every file-level `RuleStore` built
+  /// from the same core, so compiling a query once benefits every worker thread.
+  pub(crate) fn query(&self, query_str: &String) -> Arc<Query> {
+    if let Some(cached) = self.core.rule_query_cache.lock().unwrap().get(query_str) {
+      return cached.clone();
+    }
+    let compiled = Arc::new(
+      self
+        .core
+        .piranha_args
+        .piranha_language()
+        .create_query(query_str.to_string()),
+    );
     self
+      .core
       .rule_query_cache
-      .entry(query_str.to_string())
-      .or_insert_with(|| {
-        self
-          .piranha_args
-          .piranha_language()
-          .create_query(query_str.to_string())
-      })
+      .lock()
+      .unwrap()
+      .insert(query_str.to_string(), compiled.clone());
+    compiled
   }
 
   /// Get the next rules to be applied grouped by the scope in which they should be performed.
@@ -135,8 +249,8 @@ impl RuleStore {
     // let rule_name = rule.name();
     let mut next_rules: HashMap<String, Vec<Rule>> = HashMap::new();
     // Iterate over each entry (Edge) in the adjacency list corresponding to `rule_name`
-    for (scope, to_rule) in self.rule_graph.get_neighbors(rule_name) {
-      let to_rule_name = &self.rules_by_name[&to_rule];
+    for (scope, to_rule) in self.core.rule_graph.get_neighbors(rule_name) {
+      let to_rule_name = &self.core.rules_by_name[&to_rule];
       // If the to_rule_name is a dummy rule, skip it and rather return it's next rules.
       if to_rule_name.is_dummy() {
         // Call this method recursively on the dummy node
@@ -166,6 +280,7 @@ impl RuleStore {
   // For the given scope level, get the ScopeQueryGenerator from the `scope_config.toml` file
   pub(crate) fn get_scope_query_generators(&self, scope_level: &str) -> Vec<ScopeQueryGenerator> {
     self
+      .core
       .scopes
       .iter()
       .find(|level| level.name().eq(scope_level))
@@ -176,25 +291,23 @@ impl RuleStore {
   pub(crate) fn add_global_tags(&mut self, new_entries: &HashMap<String, String>) {
     let global_substitutions: HashMap<String, String> = new_entries
       .iter()
-      .filter(|e| e.0.starts_with(self.piranha_args.global_tag_prefix()))
+      .filter(|e| e.0.starts_with(self.core.piranha_args.global_tag_prefix()))
       .map(|(a, b)| (a.to_string(), b.to_string()))
       .collect();
     let _ = &self.global_tags.extend(global_substitutions);
   }
-}
 
-impl Default for RuleStore {
-  fn default() -> Self {
-    RuleStore {
-      rule_graph: RuleGraph::default(),
-      rule_query_cache: HashMap::default(),
-      rules_by_name: HashMap::default(),
-      global_rules: Vec::default(),
-      piranha_args: PiranhaArgumentsBuilder::default().build().unwrap(),
-      scopes: Vec::default(),
-      global_tags: HashMap::default(),
-      language: *PiranhaLanguage::default().language(),
-    }
+  /// Consumes this file-level store, returning the global rules it discovered beyond
+  /// its baseline and all of its global tags (including any it was seeded with), so
+  /// they can be merged (deterministically) with those produced by sibling files
+  /// after a parallel pass completes.
+  pub(crate) fn into_global_deltas(self) -> (Vec<Rule>, HashMap<String, String>) {
+    let new_rules = self
+      .global_rules
+      .into_iter()
+      .filter(|r| !self.baseline_rule_names.contains(&r.name()))
+      .collect();
+    (new_rules, self.global_tags)
   }
 }
 
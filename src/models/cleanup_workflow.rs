@@ -0,0 +1,850 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use log::debug;
+use tree_sitter::{Language, Parser, Query, QueryCursor, Range, Tree};
+
+use crate::models::{
+  piranha_arguments::PiranhaArguments, piranha_output::PiranhaOutputSummary,
+  source_code_unit::SourceCodeUnit,
+};
+
+use super::{edit::Edit, matches::Match};
+
+/// A candidate follow-up cleanup inferred from the diff between a file's content
+/// before and after a previous edit, rather than hand-authored by the rule author.
+#[derive(Debug, Clone)]
+pub(crate) enum Cleanup {
+  /// A local variable whose initializer changed from an arbitrary expression to a
+  /// boolean/constant literal - delete its declaration and replace its usages with
+  /// the literal, letting the boolean-simplification rules cascade from there.
+  InlineVariable {
+    variable_name: String,
+    literal: String,
+    declaration_range: Range,
+    usage_ranges: Vec<Range>,
+  },
+  /// A private property or method that was used before the previous edit and has
+  /// zero usages afterwards.
+  DeleteUnused {
+    symbol_name: String,
+    declaration_range: Range,
+  },
+}
+
+impl Cleanup {
+  /// Lowers this cleanup to the concrete `Edit`s that realize it, so it can be
+  /// applied through the same `SourceCodeUnit::apply_edits` path as any other batch
+  /// of edits.
+  fn to_edits(&self) -> Vec<Edit> {
+    match self {
+      Cleanup::InlineVariable {
+        literal,
+        declaration_range,
+        usage_ranges,
+        ..
+      } => {
+        let mut edits = vec![Edit::delete_range(*declaration_range)];
+        edits.extend(usage_ranges.iter().map(|range| {
+          Edit::new(
+            Match::new(*range, HashMap::new()),
+            literal.clone(),
+            "Inline Variable".to_string(),
+          )
+        }));
+        edits
+      }
+      Cleanup::DeleteUnused {
+        declaration_range, ..
+      } => vec![Edit::delete_range(*declaration_range)],
+    }
+  }
+}
+
+/// A local variable (or private property/method) declaration discovered by parsing
+/// and querying a content snapshot, together with its initializer and the ranges
+/// where it is subsequently used.
+#[derive(Debug, Clone)]
+struct Binding {
+  initializer: String,
+  declaration_range: Range,
+  enclosing_method: String,
+  usage_ranges: Vec<Range>,
+}
+
+/// Derives follow-up cleanups from a previous edit's `(old_content, new_content)`
+/// diff, so the user doesn't have to hand-author a rule edge for every cascading
+/// cleanup. Wired into `Piranha::apply`, where it loops to fixpoint alongside the
+/// other cleanup workflows until no file yields any more applicable cleanups.
+pub(crate) struct CleanupWorkflow {
+  // Maps each file touched by the previous edit to its content before and after.
+  previous_edit: HashMap<PathBuf, (String, String)>,
+  // Used to resolve the active `PiranhaLanguage` for parsing/querying content.
+  piranha_arguments: PiranhaArguments,
+}
+
+impl CleanupWorkflow {
+  pub(crate) fn new(
+    previous_edit: HashMap<PathBuf, (String, String)>, piranha_arguments: PiranhaArguments,
+  ) -> Self {
+    Self {
+      previous_edit,
+      piranha_arguments,
+    }
+  }
+
+  /// Runs the inferred cleanups against every file with an applicable diff, returning
+  /// the summaries for the cleanups that were actually applied.
+  pub(crate) fn apply(&mut self) -> Vec<PiranhaOutputSummary> {
+    let mut summaries = Vec::new();
+    for (path, (old_content, new_content)) in self.previous_edit.clone() {
+      let cleanups = self.is_applicable(&old_content, &new_content);
+      if !cleanups.is_empty() {
+        summaries.append(&mut self.apply_at_path(&path, &new_content, &cleanups));
+      }
+    }
+    summaries
+  }
+
+  /// Checks whether the previous edit makes any cleanup applicable, by diffing the
+  /// local-variable (and private property/method) bindings collected by actually
+  /// parsing and querying `old_content` and `new_content` - two genuinely different
+  /// snapshots, so their binding sets can (and do) differ.
+  ///
+  /// **Inline variable (basic)**: if a variable's initializer changed from some
+  /// expression to a boolean/constant literal, it is a candidate for inlining.
+  ///
+  /// **Delete unused private property/method**: if a symbol was used before the
+  /// change and has zero usages after, it is a candidate for deletion.
+  fn is_applicable(&self, old_content: &str, new_content: &str) -> Vec<Cleanup> {
+    let bindings_before = self.collect_bindings(old_content);
+    let bindings_after = self.collect_bindings(new_content);
+
+    let mut cleanups = Vec::new();
+    for (name, before) in &bindings_before {
+      let after = bindings_after.get(name);
+
+      if let Some(after) = after {
+        if !before.usage_ranges.is_empty() && after.usage_ranges.is_empty() {
+          debug!("'{}' has no remaining usages after the previous edit", name);
+          cleanups.push(Cleanup::DeleteUnused {
+            symbol_name: name.clone(),
+            declaration_range: after.declaration_range,
+          });
+          continue;
+        }
+        if before.initializer != after.initializer && is_boolean_or_constant_literal(&after.initializer)
+        {
+          debug!(
+            "'{}' was narrowed to the literal '{}' by the previous edit",
+            name, after.initializer
+          );
+          cleanups.push(Cleanup::InlineVariable {
+            variable_name: name.clone(),
+            literal: after.initializer.clone(),
+            declaration_range: after.declaration_range,
+            usage_ranges: after.usage_ranges.clone(),
+          });
+        }
+      } else if !before.usage_ranges.is_empty() {
+        // The declaration itself was removed by the previous edit - nothing left to do.
+        debug!("'{}' was removed by the previous edit", name);
+      }
+    }
+    cleanups
+  }
+
+  /// Parses `content` with the active `PiranhaLanguage` and runs the local-variable
+  /// declaration/usage queries over the resulting tree, collecting one `Binding` per
+  /// distinct declaration, keyed by enclosing method and name (and, for shadowing-heavy
+  /// languages, also by declaration offset - see `binding_key`).
+  fn collect_bindings(&self, content: &str) -> HashMap<String, Binding> {
+    let language = self.piranha_arguments.piranha_language();
+    collect_bindings_for(language.name(), *language.language(), content)
+  }
+
+  /// Language-specific hook that narrows usage inference to the correct binding in
+  /// shadowing-heavy languages. Go re-uses `err` (and other short names) within the
+  /// same scope via `x, err := ...`, so a usage must resolve to the *nearest
+  /// preceding* re-declaration of that name in its method, not just any declaration
+  /// sharing the name; languages without this pattern have exactly one declaration
+  /// site per (method, name) and resolve to it unconditionally.
+  fn resolves_to_same_binding(
+    &self, candidate_keys: Option<&Vec<String>>, usage_range: Range, bindings: &HashMap<String, Binding>,
+  ) -> Option<String> {
+    let nearest_preceding = shadows_within_scope(self.piranha_arguments.piranha_language().name());
+    resolves_to_same_binding(candidate_keys, usage_range, bindings, nearest_preceding)
+  }
+
+  /// Applies the inferred `cleanups` at `path`: an inline-variable cleanup deletes
+  /// its declaration and replaces every usage with the literal in one transactional
+  /// batch (which in turn triggers the built-in boolean-simplification rules via the
+  /// rule graph on the next round); a delete-unused cleanup removes the now-dead
+  /// declaration outright.
+  fn apply_at_path(
+    &mut self, path: &PathBuf, new_content: &str, cleanups: &[Cleanup],
+  ) -> Vec<PiranhaOutputSummary> {
+    let edits: Vec<Edit> = cleanups.iter().flat_map(Cleanup::to_edits).collect();
+    if edits.is_empty() {
+      return Vec::new();
+    }
+
+    let language = self.piranha_arguments.piranha_language();
+    let mut parser = Parser::new();
+    parser
+      .set_language(*language.language())
+      .expect("Could not set the tree-sitter language to apply cleanup edits");
+    let mut source_code_unit =
+      SourceCodeUnit::new(&mut parser, new_content.to_string(), path.clone());
+
+    let dropped = source_code_unit.apply_edits(&edits, &mut parser);
+    if !dropped.is_empty() {
+      debug!(
+        "{} cleanup edit(s) conflicted and were dropped at {:?}",
+        dropped.len(),
+        path
+      );
+    }
+
+    fs::write(path, source_code_unit.code()).expect("Could not write cleaned-up file");
+    vec![PiranhaOutputSummary::new(
+      path.clone(),
+      source_code_unit.code().clone(),
+      edits,
+    )]
+  }
+}
+
+/// Scope name assigned to a declaration/usage that isn't contained in any of its
+/// language's recognized function/method scopes - e.g. `language_name` has no
+/// `function_scope_query` at all, or the position is genuinely at the top level.
+/// Rather than dropping every such match (as if the query had failed), all such
+/// bindings in a file share this one scope.
+const DEFAULT_ENCLOSING_SCOPE: &str = "<file>";
+
+/// Scope shared by every private property/method binding, regardless of which class
+/// declares it or which method a usage appears in - unlike a local variable, a
+/// private member is reachable from anywhere in its class, so it can't be scoped to
+/// one `function_scope_query` range the way `enclosing_method_for` scopes locals.
+const MEMBER_SCOPE: &str = "<member>";
+
+/// The pure, `PiranhaLanguage`-free half of `CleanupWorkflow::collect_bindings`: parses
+/// `content` with `language` and runs the declaration/usage queries for `language_name`
+/// over the resulting tree. Split out so it can be unit-tested without a `PiranhaArguments`.
+fn collect_bindings_for(language_name: &str, language: Language, content: &str) -> HashMap<String, Binding> {
+  let mut parser = Parser::new();
+  parser
+    .set_language(language)
+    .expect("Could not set the tree-sitter language for the cleanup-inference parser");
+  let tree = parser
+    .parse(content, None)
+    .expect("Could not parse content for cleanup inference");
+  let bytes = content.as_bytes();
+
+  // Determined independently of the declaration/usage queries, by containment against
+  // each recognized function/method's own range - see `enclosing_method_for`.
+  let scopes = collect_function_scopes(language_name, language, &tree, bytes);
+
+  let mut bindings: HashMap<String, Binding> = HashMap::new();
+  // Ordered declaration sites per (method, name), used by `resolves_to_same_binding`
+  // to disambiguate which declaration a later usage actually refers to.
+  let mut declaration_sites: HashMap<(String, String), Vec<String>> = HashMap::new();
+  // Byte ranges of each declaration's own `@variable_name` token, so the usage query
+  // (which has no way to exclude a declaration's own LHS identifier) doesn't count a
+  // variable's own declaration site as one of its usages.
+  let mut declaration_name_ranges: std::collections::HashSet<(usize, usize)> = std::collections::HashSet::new();
+
+  let declaration_query = Query::new(language, &local_variable_declaration_query(language_name))
+    .expect("Could not compile the local-variable declaration query");
+  let mut cursor = QueryCursor::new();
+  for query_match in cursor.matches(&declaration_query, tree.root_node(), bytes) {
+    let mut variable_name = None;
+    let mut initializer = None;
+    let mut declaration_range = None;
+    for capture in query_match.captures {
+      let capture_name = &declaration_query.capture_names()[capture.index as usize];
+      let text = capture.node.utf8_text(bytes).unwrap_or_default().to_string();
+      match capture_name.as_str() {
+        "variable_name" => {
+          declaration_name_ranges.insert((capture.node.start_byte(), capture.node.end_byte()));
+          variable_name = Some(text);
+        }
+        "initializer" => initializer = Some(text),
+        "declaration" => declaration_range = Some(capture.node.range()),
+        _ => {}
+      }
+    }
+    let (Some(name), Some(declaration_range)) = (variable_name, declaration_range) else {
+      continue;
+    };
+    let enclosing_method = enclosing_method_for(declaration_range.start_byte, &scopes);
+
+    let key = binding_key(language_name, &enclosing_method, &name, declaration_range);
+    declaration_sites
+      .entry((enclosing_method.clone(), name.clone()))
+      .or_default()
+      .push(key.clone());
+    bindings.insert(
+      key,
+      Binding {
+        initializer: initializer.unwrap_or_default(),
+        declaration_range,
+        enclosing_method,
+        usage_ranges: Vec::new(),
+      },
+    );
+  }
+
+  if let Some(query_str) = private_member_declaration_query(language_name) {
+    let member_query =
+      Query::new(language, &query_str).expect("Could not compile the private-member declaration query");
+    for query_match in cursor.matches(&member_query, tree.root_node(), bytes) {
+      let mut variable_name = None;
+      let mut initializer = None;
+      let mut modifiers = None;
+      let mut declaration_range = None;
+      for capture in query_match.captures {
+        let capture_name = &member_query.capture_names()[capture.index as usize];
+        let text = capture.node.utf8_text(bytes).unwrap_or_default().to_string();
+        match capture_name.as_str() {
+          "variable_name" => {
+            declaration_name_ranges.insert((capture.node.start_byte(), capture.node.end_byte()));
+            variable_name = Some(text);
+          }
+          "initializer" => initializer = Some(text),
+          "modifiers" => modifiers = Some(text),
+          "declaration" => declaration_range = Some(capture.node.range()),
+          _ => {}
+        }
+      }
+      let (Some(name), Some(declaration_range)) = (variable_name, declaration_range) else {
+        continue;
+      };
+      if !modifiers.unwrap_or_default().contains("private") {
+        continue;
+      }
+
+      let key = binding_key(language_name, MEMBER_SCOPE, &name, declaration_range);
+      declaration_sites
+        .entry((MEMBER_SCOPE.to_string(), name.clone()))
+        .or_default()
+        .push(key.clone());
+      bindings.insert(
+        key,
+        Binding {
+          initializer: initializer.unwrap_or_default(),
+          declaration_range,
+          enclosing_method: MEMBER_SCOPE.to_string(),
+          usage_ranges: Vec::new(),
+        },
+      );
+    }
+  }
+
+  let usage_query = Query::new(language, &local_variable_usage_query(language_name))
+    .expect("Could not compile the local-variable usage query");
+  for query_match in cursor.matches(&usage_query, tree.root_node(), bytes) {
+    let mut symbol_name = None;
+    let mut usage_range = None;
+    for capture in query_match.captures {
+      let capture_name = &usage_query.capture_names()[capture.index as usize];
+      let text = capture.node.utf8_text(bytes).unwrap_or_default().to_string();
+      match capture_name.as_str() {
+        "symbol_name" => symbol_name = Some(text),
+        "usage" => usage_range = Some(capture.node.range()),
+        _ => {}
+      }
+    }
+    let (Some(name), Some(usage_range)) = (symbol_name, usage_range) else {
+      continue;
+    };
+    if declaration_name_ranges.contains(&(usage_range.start_byte, usage_range.end_byte)) {
+      // This occurrence of the identifier is the declaration's own `@variable_name`
+      // token, not a real read of the binding - skip it so it isn't double-counted.
+      continue;
+    }
+    let enclosing_method = enclosing_method_for(usage_range.start_byte, &scopes);
+
+    let local_candidates = declaration_sites.get(&(enclosing_method, name.clone()));
+    let nearest_preceding = shadows_within_scope(language_name);
+    if let Some(key) = resolves_to_same_binding(local_candidates, usage_range, &bindings, nearest_preceding) {
+      if let Some(binding) = bindings.get_mut(&key) {
+        binding.usage_ranges.push(usage_range);
+      }
+      continue;
+    }
+
+    // Not a local variable's usage - a private member is visible from any method in
+    // its class, so fall back to the class-wide member scope rather than the
+    // position's own (possibly unrelated) enclosing method.
+    let member_candidates = declaration_sites.get(&(MEMBER_SCOPE.to_string(), name));
+    if let Some(key) = resolves_to_same_binding(member_candidates, usage_range, &bindings, false) {
+      if let Some(binding) = bindings.get_mut(&key) {
+        binding.usage_ranges.push(usage_range);
+      }
+    }
+  }
+
+  bindings
+}
+
+/// Locates every named function/method scope in `tree` for `language_name`, so
+/// declarations/usages can be attributed to the function that actually contains them
+/// by byte-range containment, rather than relying on a single query pattern to
+/// capture `@enclosing_method` inline (which only Java's nesting happened to support).
+/// Returns an empty list for a language with no recognized function-declaration shape;
+/// every position in such a file falls back to `DEFAULT_ENCLOSING_SCOPE`.
+fn collect_function_scopes(
+  language_name: &str, language: Language, tree: &Tree, bytes: &[u8],
+) -> Vec<(String, Range)> {
+  let Some(query_str) = function_scope_query(language_name) else {
+    return Vec::new();
+  };
+  let query = Query::new(language, &query_str).expect("Could not compile the function-scope query");
+  let mut cursor = QueryCursor::new();
+  let mut scopes = Vec::new();
+  for query_match in cursor.matches(&query, tree.root_node(), bytes) {
+    let mut name = None;
+    let mut body_range = None;
+    for capture in query_match.captures {
+      let capture_name = &query.capture_names()[capture.index as usize];
+      match capture_name.as_str() {
+        "enclosing_method_name" => {
+          name = Some(capture.node.utf8_text(bytes).unwrap_or_default().to_string())
+        }
+        "enclosing_method_body" => body_range = Some(capture.node.range()),
+        _ => {}
+      }
+    }
+    if let (Some(name), Some(body_range)) = (name, body_range) {
+      scopes.push((name, body_range));
+    }
+  }
+  scopes
+}
+
+/// Resolves the enclosing function/method for byte offset `position`, as the name of
+/// the *innermost* (latest-starting) of `scopes`'s ranges that contains it - so a
+/// closure/nested function declared inside another one is attributed to itself, not
+/// to the function wrapping it. Falls back to `DEFAULT_ENCLOSING_SCOPE` if none
+/// contains it.
+fn enclosing_method_for(position: usize, scopes: &[(String, Range)]) -> String {
+  scopes
+    .iter()
+    .filter(|(_, range)| range.start_byte <= position && position < range.end_byte)
+    .max_by_key(|(_, range)| range.start_byte)
+    .map(|(name, _)| name.clone())
+    .unwrap_or_else(|| DEFAULT_ENCLOSING_SCOPE.to_string())
+}
+
+/// Language-specific hook that narrows usage inference to the correct binding in
+/// shadowing-heavy languages. Go re-uses `err` (and other short names) within the
+/// same scope via `x, err := ...`, so a usage must resolve to the *nearest
+/// preceding* re-declaration of that name in its method, not just any declaration
+/// sharing the name; languages without this pattern have exactly one declaration
+/// site per (method, name) and resolve to it unconditionally, as do class-wide private
+/// members. `nearest_preceding` selects the nearest-preceding resolution.
+fn resolves_to_same_binding(
+  candidate_keys: Option<&Vec<String>>, usage_range: Range, bindings: &HashMap<String, Binding>,
+  nearest_preceding: bool,
+) -> Option<String> {
+  let candidates = candidate_keys?;
+  candidates
+    .iter()
+    .filter(|key| {
+      bindings.get(*key).is_some_and(|b| {
+        !nearest_preceding || b.declaration_range.start_byte <= usage_range.start_byte
+      })
+    })
+    .max_by_key(|key| bindings[*key].declaration_range.start_byte)
+    .cloned()
+}
+
+/// A unique key for a declaration `Binding`: for shadowing-heavy languages this
+/// includes the declaration's own byte offset so that sibling re-declarations of the
+/// same name in the same method don't collapse into one binding; other languages key
+/// purely by `(method, name)` since they have at most one live declaration at a time.
+fn binding_key(language_name: &str, enclosing_method: &str, name: &str, declaration_range: Range) -> String {
+  if shadows_within_scope(language_name) {
+    format!("{enclosing_method}::{name}#{}", declaration_range.start_byte)
+  } else {
+    format!("{enclosing_method}::{name}")
+  }
+}
+
+/// Whether `language_name` commonly re-declares the same identifier within one scope
+/// (e.g. Go's `x, err := ...` re-using `err`), and therefore needs usages resolved to
+/// their nearest preceding declaration rather than a single name-keyed binding.
+fn shadows_within_scope(language_name: &str) -> bool {
+  language_name == "go"
+}
+
+/// The structural-find query that locates every named function/method scope for
+/// `language_name`, capturing its name as `@enclosing_method_name` and its full range
+/// as `@enclosing_method_body`. `None` for a language with no recognized
+/// function-declaration shape.
+fn function_scope_query(language_name: &str) -> Option<String> {
+  match language_name {
+    "java" => Some(
+      "(method_declaration name: (identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    "go" => Some(
+      "(function_declaration name: (identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    "javascript" | "typescript" | "tsx" | "jsx" => Some(
+      "[(function_declaration name: (identifier) @enclosing_method_name) (method_definition name: (property_identifier) @enclosing_method_name)] @enclosing_method_body"
+        .to_string(),
+    ),
+    "python" => Some(
+      "(function_definition name: (identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    "rust" => Some(
+      "(function_item name: (identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    "kotlin" => Some(
+      "(function_declaration (simple_identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    "swift" => Some(
+      "(function_declaration name: (simple_identifier) @enclosing_method_name) @enclosing_method_body"
+        .to_string(),
+    ),
+    _ => None,
+  }
+}
+
+/// The structural-find query used to collect local-variable declarations for
+/// `language_name`, capturing `@declaration`, `@variable_name` and `@initializer`.
+/// Matches at any nesting depth within its enclosing function - `enclosing_method_for`
+/// determines which function that is, independently of this query.
+fn local_variable_declaration_query(language_name: &str) -> String {
+  match language_name {
+    "go" => "(short_var_declaration left: (expression_list (identifier) @variable_name) right: (expression_list (_) @initializer)) @declaration".to_string(),
+    "java" => "(local_variable_declaration declarator: (variable_declarator name: (identifier) @variable_name value: (_) @initializer) @declaration)".to_string(),
+    _ => "(variable_declarator name: (identifier) @variable_name value: (_) @initializer) @declaration".to_string(),
+  }
+}
+
+/// The structural-find query that collects private property/method declarations for
+/// `language_name`, capturing `@declaration`, `@variable_name`, `@modifiers` and
+/// (for fields only) `@initializer`. `None` for a language with no recognized
+/// private-member shape - delete-unused then only ever applies to local variables.
+fn private_member_declaration_query(language_name: &str) -> Option<String> {
+  match language_name {
+    "java" => Some(
+      "[(field_declaration (modifiers) @modifiers declarator: (variable_declarator name: (identifier) @variable_name value: (_)? @initializer) @declaration) (method_declaration (modifiers) @modifiers name: (identifier) @variable_name) @declaration]"
+        .to_string(),
+    ),
+    _ => None,
+  }
+}
+
+/// The structural-find query used to collect usages of already-declared local
+/// variables for `language_name`, capturing `@usage` and `@symbol_name`. The same
+/// flat, depth-agnostic pattern is used for every language, so usages nested inside
+/// expression/assignment statements are matched too.
+fn local_variable_usage_query(_language_name: &str) -> String {
+  "(identifier) @symbol_name @usage".to_string()
+}
+
+/// Whether `expression` is a boolean literal (`true`/`false`) or another constant
+/// literal (numeric or string), as opposed to an arbitrary expression.
+fn is_boolean_or_constant_literal(expression: &str) -> bool {
+  let trimmed = expression.trim();
+  trimmed == "true"
+    || trimmed == "false"
+    || trimmed.parse::<f64>().is_ok()
+    || (trimmed.starts_with('"') && trimmed.ends_with('"'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::collect_bindings_for;
+
+  // Go's `function_scope_query` recognizes `func run() { ... }` as a real scope, so
+  // both `err` declarations resolve to the enclosing method's own name rather than a
+  // file-wide fallback; shadowing narrowing still disambiguates the two `err`s via
+  // `binding_key`'s declaration-offset suffix.
+  const GO_SHADOWED_ERR: &str = r#"
+package main
+
+func run() bool {
+	x, err := step1()
+	if err != nil {
+		return false
+	}
+	_ = x
+	y, err := step2()
+	return err == nil && y
+}
+"#;
+
+  #[test]
+  fn go_shadowed_err_gets_two_distinct_bindings_scoped_to_their_function() {
+    let bindings = collect_bindings_for("go", tree_sitter_go::language(), GO_SHADOWED_ERR);
+
+    let mut err_bindings: Vec<_> = bindings
+      .iter()
+      .filter(|(key, _)| key.starts_with("run::err#"))
+      .collect();
+    err_bindings.sort_by_key(|(_, binding)| binding.declaration_range.start_byte);
+
+    assert_eq!(
+      err_bindings.len(),
+      2,
+      "expected two shadowed `err` bindings scoped to `run`, got keys {:?}",
+      bindings.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(err_bindings[0].1.enclosing_method, "run");
+    assert_eq!(err_bindings[1].1.enclosing_method, "run");
+  }
+
+  #[test]
+  fn two_functions_with_a_same_named_local_do_not_collide() {
+    let content = r#"
+package main
+
+func first() int {
+	total := 1
+	return total
+}
+
+func second() int {
+	total := 2
+	return total
+}
+"#;
+    let bindings = collect_bindings_for("go", tree_sitter_go::language(), content);
+
+    // Go keys are offset-suffixed (see `binding_key`), so look them up by prefix.
+    let binding = |prefix: &str| {
+      bindings
+        .iter()
+        .find(|(key, _)| key.starts_with(prefix))
+        .map(|(_, binding)| binding)
+    };
+    let first_total = binding("first::total#").expect("first()'s `total` binding");
+    let second_total = binding("second::total#").expect("second()'s `total` binding");
+    assert_eq!(first_total.initializer, "1");
+    assert_eq!(second_total.initializer, "2");
+  }
+
+  #[test]
+  fn go_shadowed_err_usage_resolves_to_nearest_preceding_declaration() {
+    let bindings = collect_bindings_for("go", tree_sitter_go::language(), GO_SHADOWED_ERR);
+
+    let mut err_bindings: Vec<_> = bindings
+      .iter()
+      .filter(|(key, _)| key.starts_with("run::err#"))
+      .map(|(_, binding)| binding)
+      .collect();
+    err_bindings.sort_by_key(|binding| binding.declaration_range.start_byte);
+    let (first_err, second_err) = (err_bindings[0], err_bindings[1]);
+
+    // Each `err` was only ever read once (`err != nil` for the first, `err == nil`
+    // for the second) - a usage resolving to the wrong declaration, or a
+    // declaration's own LHS token being double-counted as a usage, would throw this
+    // off.
+    assert_eq!(first_err.usage_ranges.len(), 1);
+    assert_eq!(second_err.usage_ranges.len(), 1);
+    assert!(first_err.usage_ranges[0].start_byte < second_err.declaration_range.start_byte);
+    assert!(second_err.usage_ranges[0].start_byte > second_err.declaration_range.start_byte);
+  }
+
+  #[test]
+  fn a_declaration_s_own_identifier_is_not_counted_as_its_own_usage() {
+    let content = r#"
+package main
+
+func run() int {
+	total := 1
+	return total
+}
+"#;
+    let bindings = collect_bindings_for("go", tree_sitter_go::language(), content);
+
+    let total = bindings
+      .iter()
+      .find(|(key, _)| key.starts_with("run::total#"))
+      .map(|(_, binding)| binding)
+      .expect("run()'s `total` binding");
+    // `total` appears twice in the source: once as the declaration's own LHS token
+    // and once in `return total`. Only the latter is a real usage.
+    assert_eq!(total.usage_ranges.len(), 1);
+  }
+
+  // JavaScript's `function_scope_query` captures both `function_declaration` and
+  // `method_definition`, so a same-named local in two sibling functions must not
+  // collide - the same containment logic Go's test above covers, on a different
+  // language's query shape.
+  #[test]
+  fn js_two_functions_with_a_same_named_local_do_not_collide() {
+    let content = r#"
+function first() {
+  let total = 1;
+  return total;
+}
+
+function second() {
+  let total = 2;
+  return total;
+}
+"#;
+    let bindings = collect_bindings_for(
+      "javascript",
+      tree_sitter_javascript::language(),
+      content,
+    );
+
+    let first_total = bindings
+      .get("first::total")
+      .expect("first()'s `total` binding");
+    let second_total = bindings
+      .get("second::total")
+      .expect("second()'s `total` binding");
+    assert_eq!(first_total.initializer, "1");
+    assert_eq!(second_total.initializer, "2");
+    // Each `total` is only read once (`return total`) - the declaration's own LHS
+    // token must not be double-counted as a usage.
+    assert_eq!(first_total.usage_ranges.len(), 1);
+    assert_eq!(second_total.usage_ranges.len(), 1);
+  }
+
+  // A function declared inside another function creates two nested, overlapping
+  // scopes - `enclosing_method_for` must attribute a declaration/usage to the
+  // *innermost* one that contains it, not the outer function just because it also
+  // contains the position.
+  #[test]
+  fn nested_function_declaration_is_its_own_innermost_scope() {
+    let content = r#"
+function outer() {
+  let total = 1;
+
+  function inner() {
+    let total = 2;
+    return total;
+  }
+
+  return total + inner();
+}
+"#;
+    let bindings = collect_bindings_for(
+      "javascript",
+      tree_sitter_javascript::language(),
+      content,
+    );
+
+    let outer_total = bindings
+      .get("outer::total")
+      .expect("outer()'s `total` binding");
+    let inner_total = bindings
+      .get("inner::total")
+      .expect("inner()'s `total` binding, not outer()'s");
+
+    assert_eq!(outer_total.enclosing_method, "outer");
+    assert_eq!(inner_total.enclosing_method, "inner");
+    assert_eq!(outer_total.usage_ranges.len(), 1);
+    assert_eq!(inner_total.usage_ranges.len(), 1);
+  }
+
+  // Java is the only language with a `private_member_declaration_query` today - other
+  // languages' declarations/usages are only ever resolved as local variables.
+  #[test]
+  fn java_private_members_are_scoped_class_wide_not_per_method() {
+    let content = r#"
+class Foo {
+  private int count = 0;
+
+  public void increment() {
+    count = count + 1;
+  }
+
+  private void helper() {
+    System.out.println("x");
+  }
+}
+"#;
+    let bindings = collect_bindings_for("java", tree_sitter_java::language(), content);
+
+    let count = bindings
+      .get("<member>::count")
+      .expect("private field `count` binding");
+    assert_eq!(count.enclosing_method, "<member>");
+    assert_eq!(
+      count.usage_ranges.len(),
+      2,
+      "both occurrences of `count` inside increment() - the read and the write - are usages"
+    );
+
+    let helper = bindings
+      .get("<member>::helper")
+      .expect("private method `helper` binding");
+    assert!(helper.usage_ranges.is_empty(), "helper() is never called");
+
+    assert!(
+      !bindings.contains_key("<member>::increment"),
+      "increment() is public, not private, and should not be tracked as a member binding"
+    );
+  }
+
+  // Exercises the actual before/after shape `CleanupWorkflow::is_applicable` diffs: a
+  // private method with a usage in the "before" snapshot and none in the "after" one
+  // is exactly the signal `Cleanup::DeleteUnused` looks for.
+  #[test]
+  fn java_private_member_usage_disappears_across_a_diff() {
+    let before = r#"
+class Foo {
+  private void helper() {
+    System.out.println("x");
+  }
+
+  public void run() {
+    helper();
+  }
+}
+"#;
+    let after = r#"
+class Foo {
+  private void helper() {
+    System.out.println("x");
+  }
+
+  public void run() {
+  }
+}
+"#;
+    let bindings_before = collect_bindings_for("java", tree_sitter_java::language(), before);
+    let bindings_after = collect_bindings_for("java", tree_sitter_java::language(), after);
+
+    let helper_before = bindings_before
+      .get("<member>::helper")
+      .expect("helper() binding before the edit");
+    let helper_after = bindings_after
+      .get("<member>::helper")
+      .expect("helper() binding after the edit");
+
+    assert_eq!(helper_before.usage_ranges.len(), 1, "called once, from run()");
+    assert!(
+      helper_after.usage_ranges.is_empty(),
+      "the call site was removed, so helper() is now unused"
+    );
+  }
+}
@@ -15,7 +15,7 @@ use std::collections::HashMap;
 
 use getset::Getters;
 use serde_derive::Serialize;
-use tree_sitter::Range;
+use tree_sitter::{Point, Range};
 
 use super::matches::Match;
 use pyo3::prelude::pyclass;
@@ -53,4 +53,37 @@ impl Edit {
       matched_rule: "Delete Range".to_string(),
     }
   }
+
+  /// Creates an edit that inserts `text` at `offset` without replacing any existing
+  /// bytes, e.g. for prepending a license header to a file that doesn't have one yet.
+  /// Backed by a zero-width `Range` at `offset`, so it composes with the rest of the
+  /// `Edit`/`SourceCodeUnit` machinery like any other edit. `source` is the full file
+  /// content `offset` is measured against, used to derive the insertion point's
+  /// row/column; the zero-width range's start and end point are both that one point.
+  pub(crate) fn insert_at(offset: usize, source: &str, text: String) -> Self {
+    let point = point_at(source, offset);
+    let zero_width_range = Range {
+      start_byte: offset,
+      end_byte: offset,
+      start_point: point,
+      end_point: point,
+    };
+    Self {
+      p_match: Match::new(zero_width_range, HashMap::new()),
+      replacement_string: text,
+      matched_rule: "Insert At".to_string(),
+    }
+  }
+}
+
+/// Computes the `tree_sitter::Point` (row/column) of byte offset `offset` within
+/// `source`, counting newlines preceding it.
+pub(crate) fn point_at(source: &str, offset: usize) -> Point {
+  let preceding = &source[..offset];
+  let row = preceding.matches('\n').count();
+  let column = match preceding.rfind('\n') {
+    Some(idx) => offset - idx - 1,
+    None => offset,
+  };
+  Point { row, column }
 }
@@ -0,0 +1,247 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use tree_sitter::Range;
+
+use super::{
+  edit::{point_at, Edit},
+  language::PiranhaLanguage,
+  matches::Match,
+};
+
+static SPDX_TAG: &str = "SPDX-License-Identifier:";
+
+/// The REUSE-style configuration driving the built-in license-header cleanup: the
+/// `SPDX-License-Identifier` every file must carry, and the copyright line to
+/// accompany it when a header has to be inserted from scratch.
+#[derive(Debug, Clone)]
+pub(crate) struct SpdxHeaderConfig {
+  spdx_identifier: String,
+  copyright_line: String,
+}
+
+impl SpdxHeaderConfig {
+  pub(crate) fn new(spdx_identifier: String, copyright_line: String) -> Self {
+    Self {
+      spdx_identifier,
+      copyright_line,
+    }
+  }
+}
+
+/// The comment syntax a language renders/recognizes an SPDX header in: C-family
+/// languages and Rust conventionally use a single block comment (see the header on
+/// this very file), languages whose convention is a run of line comments (Go,
+/// JS/TS, Kotlin, Swift) get one, and languages without a block-comment syntax at
+/// all (Python, Ruby) fall back to line comments too.
+enum CommentStyle {
+  Line(&'static str),
+  Block {
+    open: &'static str,
+    close: &'static str,
+  },
+}
+
+/// Checks whether `content`'s leading comment already carries an
+/// `SPDX-License-Identifier` matching `config`, and returns the `Edit` needed to bring
+/// it in line, if any:
+/// - `None` if a matching identifier is already present - nothing to do.
+/// - an `Edit::insert_at(0, ..)` prepending a correctly-formatted header (using
+///   `language`'s comment syntax) if the file has no SPDX header at all.
+/// - an in-place `Edit` rewriting just the identifier if one is present but stale.
+pub(crate) fn spdx_header_edit(
+  content: &str, language: &PiranhaLanguage, config: &SpdxHeaderConfig,
+) -> Option<Edit> {
+  let style = comment_style(language);
+  match find_existing_identifier(content, &style) {
+    Some((_, _, existing)) if existing == config.spdx_identifier => None,
+    Some((start_byte, end_byte, _)) => Some(Edit::new(
+      Match::new(byte_range(content, start_byte, end_byte), HashMap::new()),
+      config.spdx_identifier.clone(),
+      "Normalize SPDX License Header".to_string(),
+    )),
+    None => Some(Edit::insert_at(0, content, render_header(&style, config))),
+  }
+}
+
+/// Returns the comment syntax `language` renders/recognizes an SPDX header in,
+/// matching that language's own convention rather than defaulting every language to
+/// a block comment.
+fn comment_style(language: &PiranhaLanguage) -> CommentStyle {
+  match language.name() {
+    "python" | "ruby" => CommentStyle::Line("#"),
+    "go" | "javascript" | "typescript" | "tsx" | "jsx" | "kotlin" | "swift" => CommentStyle::Line("//"),
+    _ => CommentStyle::Block {
+      open: "/*",
+      close: "*/",
+    },
+  }
+}
+
+/// Renders a fresh, correctly-formatted SPDX header for `config` in `style`,
+/// terminated by a blank line so it reads as its own block ahead of whatever follows
+/// in the file.
+fn render_header(style: &CommentStyle, config: &SpdxHeaderConfig) -> String {
+  match style {
+    CommentStyle::Line(marker) => format!(
+      "{marker} {SPDX_TAG} {}\n{marker} Copyright {}\n\n",
+      config.spdx_identifier, config.copyright_line
+    ),
+    CommentStyle::Block { open, close } => format!(
+      "{open}\n{SPDX_TAG} {}\nCopyright {}\n{close}\n\n",
+      config.spdx_identifier, config.copyright_line
+    ),
+  }
+}
+
+/// Scans the leading comment at the top of `content` - a run of line comments, or a
+/// single block comment, depending on `style` - for an `SPDX-License-Identifier` tag,
+/// returning the byte range of just its value (not the whole line) along with the
+/// value itself.
+fn find_existing_identifier(
+  content: &str, style: &CommentStyle,
+) -> Option<(usize, usize, String)> {
+  match style {
+    CommentStyle::Line(marker) => find_identifier_in_line_comments(content, marker),
+    CommentStyle::Block { open, close } => find_identifier_in_block_comment(content, open, close),
+  }
+}
+
+/// Scans the leading run of comment lines at the top of `content` (stopping at the
+/// first non-comment line) for the SPDX tag.
+fn find_identifier_in_line_comments(
+  content: &str, marker: &str,
+) -> Option<(usize, usize, String)> {
+  let mut offset = 0;
+  for line in content.split_inclusive('\n') {
+    if !line.trim_start().starts_with(marker) {
+      break;
+    }
+    if let Some(tag_idx) = line.find(SPDX_TAG) {
+      let after_tag = tag_idx + SPDX_TAG.len();
+      let rest = line[after_tag..].trim_end_matches(['\r', '\n']);
+      let value = rest.trim();
+      let value_start = after_tag + (rest.len() - rest.trim_start().len());
+      let value_end = value_start + value.len();
+      return Some((offset + value_start, offset + value_end, value.to_string()));
+    }
+    offset += line.len();
+  }
+  None
+}
+
+/// Looks for the SPDX tag inside `content`'s leading block comment, if `content`
+/// starts with one (ignoring leading whitespace).
+fn find_identifier_in_block_comment(
+  content: &str, open: &str, close: &str,
+) -> Option<(usize, usize, String)> {
+  let trimmed_start = content.len() - content.trim_start().len();
+  let body = content[trimmed_start..].strip_prefix(open)?;
+  let close_idx = body.find(close)?;
+  let block = &body[..close_idx];
+  let block_offset = trimmed_start + open.len();
+
+  let tag_idx = block.find(SPDX_TAG)?;
+  let after_tag = tag_idx + SPDX_TAG.len();
+  let rest = &block[after_tag..];
+  let line_end = rest.find('\n').unwrap_or(rest.len());
+  let line = &rest[..line_end];
+  let value = line.trim();
+  let value_start = after_tag + (line.len() - line.trim_start().len());
+  let value_end = value_start + value.len();
+  Some((
+    block_offset + value_start,
+    block_offset + value_end,
+    value.to_string(),
+  ))
+}
+
+/// Computes the `tree_sitter::Range` for the byte span `[start_byte, end_byte)` of
+/// `content`, deriving row/column from the number of newlines preceding each offset.
+fn byte_range(content: &str, start_byte: usize, end_byte: usize) -> Range {
+  Range {
+    start_byte,
+    end_byte,
+    start_point: point_at(content, start_byte),
+    end_point: point_at(content, end_byte),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config() -> SpdxHeaderConfig {
+    SpdxHeaderConfig::new("Apache-2.0".to_string(), "2026 Example Corp.".to_string())
+  }
+
+  #[test]
+  fn finds_identifier_in_line_comments() {
+    let content = "# SPDX-License-Identifier: MIT\n# Copyright 2020\n\nprint('hi')\n";
+    let (start, end, value) = find_identifier_in_line_comments(content, "#").unwrap();
+    assert_eq!(value, "MIT");
+    assert_eq!(&content[start..end], "MIT");
+  }
+
+  #[test]
+  fn stops_scanning_line_comments_at_the_first_non_comment_line() {
+    let content = "code();\n# SPDX-License-Identifier: MIT\n";
+    assert_eq!(find_identifier_in_line_comments(content, "#"), None);
+  }
+
+  #[test]
+  fn finds_identifier_in_block_comment() {
+    let content = "/*\nSPDX-License-Identifier: MIT\nCopyright 2020\n*/\n\nfn main() {}\n";
+    let (start, end, value) = find_identifier_in_block_comment(content, "/*", "*/").unwrap();
+    assert_eq!(value, "MIT");
+    assert_eq!(&content[start..end], "MIT");
+  }
+
+  #[test]
+  fn block_comment_lookup_is_none_without_a_leading_block_comment() {
+    let content = "// SPDX-License-Identifier: MIT\nfn main() {}\n";
+    assert_eq!(find_identifier_in_block_comment(content, "/*", "*/"), None);
+  }
+
+  #[test]
+  fn renders_line_style_and_block_style_headers() {
+    let cfg = config();
+    assert_eq!(
+      render_header(&CommentStyle::Line("#"), &cfg),
+      "# SPDX-License-Identifier: Apache-2.0\n# Copyright 2026 Example Corp.\n\n"
+    );
+    assert_eq!(
+      render_header(
+        &CommentStyle::Block {
+          open: "/*",
+          close: "*/"
+        },
+        &cfg
+      ),
+      "/*\nSPDX-License-Identifier: Apache-2.0\nCopyright 2026 Example Corp.\n*/\n\n"
+    );
+  }
+
+  #[test]
+  fn find_existing_identifier_dispatches_on_comment_style() {
+    let content = "/*\nSPDX-License-Identifier: Apache-2.0\n*/\n";
+    let style = CommentStyle::Block {
+      open: "/*",
+      close: "*/",
+    };
+    let (_, _, existing) = find_existing_identifier(content, &style).unwrap();
+    assert_eq!(existing, config().spdx_identifier);
+  }
+}
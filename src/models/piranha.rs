@@ -0,0 +1,93 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use crate::models::{
+  cleanup_workflow::CleanupWorkflow, piranha_arguments::PiranhaArguments,
+  piranha_output::PiranhaOutputSummary, seed_workflow::SeedWorkflow, step::Step,
+};
+
+/// Top-level driver for a Piranha run: applies the seed edit, then repeatedly runs
+/// the `CleanupWorkflow`s against whatever the previous round changed - re-running the
+/// seed rule graph against just the touched file(s) after each round so built-in rules
+/// (e.g. boolean simplification) cascade off an inlined literal - until a round
+/// produces no further cleanups.
+pub(crate) struct Piranha {
+  seed_piranha_argument: PiranhaArguments,
+  seed_workflow: SeedWorkflow,
+}
+
+impl Piranha {
+  pub(crate) fn new(seed_piranha_argument: PiranhaArguments) -> Self {
+    Self {
+      seed_workflow: SeedWorkflow::new(seed_piranha_argument.clone()),
+      seed_piranha_argument,
+    }
+  }
+
+  pub(crate) fn apply(&mut self) -> Vec<PiranhaOutputSummary> {
+    let mut output_summary = self.seed_workflow.apply();
+
+    loop {
+      let previous_edit = diff_by_path(&output_summary);
+      if previous_edit.is_empty() {
+        break;
+      }
+
+      let mut cleanup_performed = false;
+      for path in previous_edit.keys().cloned().collect::<Vec<_>>() {
+        let (old_content, new_content) = previous_edit[&path].clone();
+        let mut workflow = CleanupWorkflow::new(
+          HashMap::from([(path.clone(), (old_content, new_content))]),
+          self.seed_piranha_argument.clone(),
+        );
+        let mut cleanup_summary = workflow.apply();
+        if !cleanup_summary.is_empty() {
+          output_summary.append(&mut cleanup_summary);
+          cleanup_performed = true;
+
+          // Re-run the seed rule graph against just this file, so a built-in rule
+          // (e.g. boolean simplification) can cascade off the cleanup that was just
+          // applied before the next round's diff is taken.
+          let cascade_args = self.seed_piranha_argument.with_paths_to_process(vec![path]);
+          let mut cascade_summary = Step::new(cascade_args).apply().clone();
+          if !cascade_summary.is_empty() {
+            output_summary.append(&mut cascade_summary);
+          }
+        }
+      }
+      if !cleanup_performed {
+        break;
+      }
+    }
+
+    output_summary
+  }
+}
+
+/// Groups the latest output summaries by file path, pairing each file's content
+/// before and after its most recent edit, to feed the next round of cleanup inference.
+fn diff_by_path(
+  summaries: &[PiranhaOutputSummary],
+) -> HashMap<std::path::PathBuf, (String, String)> {
+  summaries
+    .iter()
+    .map(|summary| {
+      (
+        summary.path().clone(),
+        (summary.original_content().clone(), summary.content().clone()),
+      )
+    })
+    .collect()
+}
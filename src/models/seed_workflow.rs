@@ -0,0 +1,33 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use crate::models::{piranha_arguments::PiranhaArguments, piranha_output::PiranhaOutputSummary, step::Step};
+
+/// The user-authored edit that kicks off a `Piranha` run - a single `Step` driven by
+/// the seed `PiranhaArguments` the caller configured. Its output seeds the
+/// `CleanupWorkflow`s that then loop to fixpoint.
+pub(crate) struct SeedWorkflow {
+  seed_step: Step,
+}
+
+impl SeedWorkflow {
+  pub(crate) fn new(piranha_arguments: PiranhaArguments) -> Self {
+    Self {
+      seed_step: Step::new(piranha_arguments),
+    }
+  }
+
+  pub(crate) fn apply(&mut self) -> Vec<PiranhaOutputSummary> {
+    self.seed_step.apply().clone()
+  }
+}
@@ -0,0 +1,235 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use std::path::PathBuf;
+
+use getset::Getters;
+use log::debug;
+use tree_sitter::{Parser, Tree};
+
+use super::edit::Edit;
+
+/// Represents the source code of a single file together with its parsed `Tree`.
+/// All mutations to the file are funnelled through this struct so that `code`
+/// and `ast` never drift out of sync.
+#[derive(Getters)]
+pub(crate) struct SourceCodeUnit {
+  // The tree-sitter parse tree for `code`
+  ast: Tree,
+  // The content of the file, kept in sync with `ast`
+  #[get = "pub"]
+  code: String,
+  // Path to the file on disk
+  #[get = "pub"]
+  path: PathBuf,
+}
+
+impl SourceCodeUnit {
+  pub(crate) fn new(parser: &mut Parser, code: String, path: PathBuf) -> Self {
+    let ast = parser.parse(&code, None).expect("Could not parse code");
+    Self { ast, code, path }
+  }
+
+  /// Applies a single `edit` to `code` and re-parses the whole file.
+  ///
+  /// This is correct but wasteful when a rule produces many independent matches
+  /// within the same file - each edit pays for its own re-parse. Prefer
+  /// [`SourceCodeUnit::apply_edits`] when applying a batch of non-overlapping edits.
+  pub(crate) fn apply_edit(&mut self, edit: &Edit, parser: &mut Parser) {
+    let range = edit.p_match().range();
+    let mut new_code = self.code.clone();
+    new_code.replace_range(range.start_byte..range.end_byte, edit.replacement_string());
+    self.code = new_code;
+    self.ast = parser.parse(&self.code, None).expect("Could not re-parse code");
+  }
+
+  /// Applies a whole batch of (expected to be) non-overlapping `edits` in a single pass,
+  /// re-parsing only once at the end, modeled on rustfix's `replace.rs`.
+  ///
+  /// The original source is represented as an ordered vector of [`Part`]s, each of which
+  /// is either an untouched original byte-span or an already-substituted replacement.
+  /// Edits whose target range has already been consumed by an earlier edit in this batch
+  /// (a genuine conflict) are skipped rather than corrupting the buffer, and are returned
+  /// to the caller for reporting.
+  pub(crate) fn apply_edits(&mut self, edits: &[Edit], parser: &mut Parser) -> Vec<Edit> {
+    let (new_code, dropped_edits) = apply_edits_to_str(&self.code, edits);
+    self.code = new_code;
+    self.ast = parser.parse(&self.code, None).expect("Could not re-parse code");
+    dropped_edits
+  }
+}
+
+/// The pure part of [`SourceCodeUnit::apply_edits`]: applies every non-conflicting edit
+/// in `edits` to `code` and returns the resulting string alongside whichever edits had
+/// to be dropped. Split out so the overlap/conflict logic can be unit-tested without a
+/// `Parser`.
+fn apply_edits_to_str(code: &str, edits: &[Edit]) -> (String, Vec<Edit>) {
+  let mut sorted_edits = edits.to_vec();
+  sorted_edits.sort_by_key(|e| e.p_match().range().start_byte);
+
+  let mut parts = vec![Part::Original {
+    start: 0,
+    end: code.len(),
+  }];
+  let mut dropped_edits = Vec::new();
+  let mut previous_end = 0;
+
+  for edit in sorted_edits {
+    let range = edit.p_match().range();
+    let (start, end) = (range.start_byte, range.end_byte);
+
+    // Edits are expected to be disjoint. If this one starts before the previous one
+    // ended, it strictly overlaps a just-applied edit - reject it up front.
+    if start < previous_end {
+      debug!(
+        "Dropping overlapping edit for rule {} at [{}, {})",
+        edit.matched_rule(),
+        start,
+        end
+      );
+      dropped_edits.push(edit);
+      continue;
+    }
+
+    match split_part(&mut parts, start, end, edit.replacement_string()) {
+      Ok(()) => previous_end = end,
+      Err(()) => {
+        debug!(
+          "Dropping conflicting edit for rule {} at [{}, {}) - target region already replaced",
+          edit.matched_rule(),
+          start,
+          end
+        );
+        dropped_edits.push(edit);
+      }
+    }
+  }
+
+  let new_code: String = parts
+    .iter()
+    .map(|part| match part {
+      Part::Original { start, end } => &code[*start..*end],
+      Part::Replaced(text) => text.as_str(),
+    })
+    .collect();
+
+  (new_code, dropped_edits)
+}
+
+/// A contiguous span of the buffer being rewritten by [`SourceCodeUnit::apply_edits`].
+enum Part {
+  // An untouched byte-span `[start, end)` of the original source
+  Original { start: usize, end: usize },
+  // A replacement string that has already been substituted in
+  Replaced(String),
+}
+
+/// Locates the `Original` part of `parts` that fully contains `[start, end)`, splits it at
+/// the `start`/`end` boundaries and marks the middle region `Replaced` with `replacement`.
+/// Returns `Err(())` if no such untouched part exists (i.e. the region was already replaced).
+fn split_part(parts: &mut Vec<Part>, start: usize, end: usize, replacement: &str) -> Result<(), ()> {
+  for i in 0..parts.len() {
+    if let Part::Original {
+      start: p_start,
+      end: p_end,
+    } = parts[i]
+    {
+      if start >= p_start && end <= p_end {
+        let mut replacement_parts = Vec::with_capacity(3);
+        if p_start < start {
+          replacement_parts.push(Part::Original {
+            start: p_start,
+            end: start,
+          });
+        }
+        replacement_parts.push(Part::Replaced(replacement.to_string()));
+        if end < p_end {
+          replacement_parts.push(Part::Original { start: end, end: p_end });
+        }
+        parts.splice(i..=i, replacement_parts);
+        return Ok(());
+      }
+    }
+  }
+  Err(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use tree_sitter::{Point, Range};
+
+  use super::{apply_edits_to_str, split_part, Edit, Part};
+  use crate::models::matches::Match;
+
+  fn zero_point() -> Point {
+    Point { row: 0, column: 0 }
+  }
+
+  fn edit_at(start_byte: usize, end_byte: usize, replacement: &str, rule_name: &str) -> Edit {
+    let range = Range {
+      start_byte,
+      end_byte,
+      start_point: zero_point(),
+      end_point: zero_point(),
+    };
+    Edit::new(
+      Match::new(range, HashMap::new()),
+      replacement.to_string(),
+      rule_name.to_string(),
+    )
+  }
+
+  #[test]
+  fn applies_disjoint_edits_in_any_order() {
+    let code = "let x = 1; let y = 2;";
+    let edits = vec![
+      edit_at(19, 20, "20", "rule_a"),
+      edit_at(8, 9, "10", "rule_b"),
+    ];
+
+    let (new_code, dropped) = apply_edits_to_str(code, &edits);
+
+    assert_eq!(new_code, "let x = 10; let y = 20;");
+    assert!(dropped.is_empty());
+  }
+
+  #[test]
+  fn drops_edit_that_overlaps_an_already_applied_edit() {
+    let code = "let x = 1;";
+    // [4, 9) ("x = 1") and [6, 7) ("=") overlap - the second is rejected up front
+    // since it starts before the first edit's end.
+    let edits = vec![
+      edit_at(4, 9, "y = 2", "rule_first"),
+      edit_at(6, 7, "9", "rule_overlapping"),
+    ];
+
+    let (new_code, dropped) = apply_edits_to_str(code, &edits);
+
+    assert_eq!(new_code, "let y = 2;");
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].matched_rule(), "rule_overlapping");
+  }
+
+  #[test]
+  fn split_part_rejects_a_range_that_straddles_an_already_replaced_part() {
+    // [0, 10) replaced first, leaving parts = [Replaced, Original{10, 20}]. A second
+    // range reaching back into the replaced part, like [5, 15), can't be fully
+    // contained in any single remaining `Original` part - a genuine conflict.
+    let mut parts = vec![Part::Original { start: 0, end: 20 }];
+    assert_eq!(split_part(&mut parts, 0, 10, "x"), Ok(()));
+
+    assert_eq!(split_part(&mut parts, 5, 15, "y"), Err(()));
+  }
+}
@@ -0,0 +1,43 @@
+/*
+Copyright (c) 2022 Uber Technologies, Inc.
+
+ <p>Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file
+ except in compliance with the License. You may obtain a copy of the License at
+ <p>http://www.apache.org/licenses/LICENSE-2.0
+
+ <p>Unless required by applicable law or agreed to in writing, software distributed under the
+ License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either
+ express or implied. See the License for the specific language governing permissions and
+ limitations under the License.
+*/
+
+use crate::execute_piranha;
+use crate::models::{piranha_arguments::PiranhaArguments, piranha_output::PiranhaOutputSummary};
+
+/// A single invocation of the rule-graph engine against one `PiranhaArguments`
+/// configuration. `SeedWorkflow` constructs one for the initial run, and
+/// `Piranha::apply` constructs another after each `CleanupWorkflow` round - scoped to
+/// just the file(s) that round touched - so built-in rules (e.g. boolean
+/// simplification) cascade off an inferred cleanup.
+pub(crate) struct Step {
+  piranha_arguments: PiranhaArguments,
+  summaries: Vec<PiranhaOutputSummary>,
+}
+
+impl Step {
+  pub(crate) fn new(piranha_arguments: PiranhaArguments) -> Self {
+    Self {
+      piranha_arguments,
+      summaries: Vec::new(),
+    }
+  }
+
+  pub(crate) fn apply(&mut self) -> &Vec<PiranhaOutputSummary> {
+    self.summaries = execute_piranha(&self.piranha_arguments);
+    &self.summaries
+  }
+
+  pub(crate) fn summaries(&self) -> &Vec<PiranhaOutputSummary> {
+    &self.summaries
+  }
+}